@@ -1,73 +1,191 @@
 use base64::prelude::*;
-use prometheus_client::encoding::EncodeLabelSet;
+use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue, LabelSetEncoder};
+use proto::mod_TopicDescriptor::{AuthOpts, EncOpts};
 use quick_protobuf::Writer;
-use serde::{Deserialize, Serialize};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sha2::{Digest, Sha256};
 use std::fmt;
+use std::str::FromStr;
 
 /// A generic trait that can be extended for various hashing types for a topic.
 pub trait Hasher {
-    /// The function that takes a topic string and creates a topic hash.
-    fn hash(topic_string: String) -> TopicHash;
+    /// Takes a topic string and the topic's auth/enc policy and creates a topic hash.
+    /// Hashers that don't support access control (e.g. [`IdentityHash`]) may ignore
+    /// `auth`/`enc`; hashers that hash the encoded [`proto::TopicDescriptor`] (e.g.
+    /// [`Sha256Hash`]) must fold them into the hash so that two topics with the same
+    /// name but different policies never collide.
+    fn hash(topic_string: String, auth: Option<AuthOpts>, enc: Option<EncOpts>) -> TopicHash;
+}
+
+/// An object-safe counterpart to [`Hasher`].
+///
+/// [`Hasher`] is a bare associated function with no `&self`, which forces every
+/// [`Topic<H>`] to be monomorphized over a compile-time hash type and makes it impossible
+/// to hold topics that use different hash algorithms in one collection -- e.g. a
+/// `HashMap<TopicHash, Subscription>` populated from peers using different hashers.
+/// `DynHasher` trades the richer auth/enc-aware [`Hasher::hash`] for an object-safe
+/// `&self` method so hashers can be boxed, chosen at runtime, and stored heterogeneously
+/// in [`AnyTopic`].
+pub trait DynHasher: fmt::Debug {
+    /// Hashes `topic` using this hasher's algorithm, with no auth/enc policy.
+    fn hash(&self, topic: &str) -> TopicHash;
+}
+
+impl<H: Hasher + fmt::Debug + Default> DynHasher for H {
+    fn hash(&self, topic: &str) -> TopicHash {
+        H::hash(topic.to_owned(), None, None)
+    }
 }
 
 /// A type for representing topics who use the identity hash.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct IdentityHash {}
 impl Hasher for IdentityHash {
-    /// Creates a [`TopicHash`] as a raw string.
-    fn hash(topic_string: String) -> TopicHash {
-        TopicHash { hash: topic_string }
+    /// Creates a [`TopicHash`] as a raw string. The identity hash has no concept of an
+    /// auth/enc policy, so `auth` and `enc` are ignored.
+    fn hash(topic_string: String, _auth: Option<AuthOpts>, _enc: Option<EncOpts>) -> TopicHash {
+        TopicHash::from_raw(topic_string)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Sha256Hash {}
 impl Hasher for Sha256Hash {
-    /// Creates a [`TopicHash`] by SHA256 hashing the topic then base64 encoding the
-    /// hash.
-    fn hash(topic_string: String) -> TopicHash {
+    /// Creates a [`TopicHash`] by SHA256 hashing the encoded topic descriptor -- including
+    /// the `auth`/`enc` policy, if any -- then base64 encoding the hash.
+    fn hash(topic_string: String, auth: Option<AuthOpts>, enc: Option<EncOpts>) -> TopicHash {
         use quick_protobuf::MessageWrite;
 
         let topic_descripter = proto::TopicDescriptor {
             name: Some(topic_string),
-            auth: None,
-            enc: None,
+            auth,
+            enc,
+        };
+        let mut bytes = Vec::with_capacity(topic_descripter.get_size());
+        let mut writer = Writer::new(&mut bytes);
+        topic_descripter
+            .write_message(&mut writer)
+            .expect("Encoding to succeed");
+        TopicHash::from_bytes(Sha256::digest(&bytes).to_vec())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Blake3Hash {}
+impl Hasher for Blake3Hash {
+    /// Creates a [`TopicHash`] by BLAKE3 hashing the encoded topic descriptor -- including
+    /// the `auth`/`enc` policy, if any -- and rendering it as lowercase hex, matching how
+    /// iroh's own `Hash`/`HashAndFormat` types serialize. This lets a blob `Hash` be reused
+    /// directly as a topic seed without pulling in a second crypto dependency path.
+    fn hash(topic_string: String, auth: Option<AuthOpts>, enc: Option<EncOpts>) -> TopicHash {
+        use quick_protobuf::MessageWrite;
+
+        let topic_descripter = proto::TopicDescriptor {
+            name: Some(topic_string),
+            auth,
+            enc,
         };
         let mut bytes = Vec::with_capacity(topic_descripter.get_size());
         let mut writer = Writer::new(&mut bytes);
         topic_descripter
             .write_message(&mut writer)
             .expect("Encoding to succeed");
-        let hash = BASE64_STANDARD.encode(Sha256::digest(&bytes));
-        TopicHash { hash }
+        TopicHash::from_bytes_hex(blake3::hash(&bytes).as_bytes().to_vec())
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, EncodeLabelSet, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct TopicHash {
-    /// The topic hash. Stored as a string to align with the protobuf API.
-    hash: String,
+    /// The raw digest (or, for [`IdentityHash`], the UTF-8 bytes of the topic name).
+    hash: Vec<u8>,
+    /// How `hash` renders via [`fmt::Display`]. Hex-vs-base64 is purely presentational --
+    /// it doesn't affect equality, hashing, or the serialized form (see `Serialize`), which
+    /// is base64 of `hash` in human-readable formats and the raw `hash` bytes otherwise.
+    /// `PartialEq`/`Eq`/`Hash`/`Ord` are implemented by hand below
+    /// against `hash` alone so that two hashers which picked different display encodings
+    /// for the same digest still compare and hash as the same topic.
+    display: String,
+}
+
+impl PartialEq for TopicHash {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash
+    }
+}
+
+impl Eq for TopicHash {}
+
+impl std::hash::Hash for TopicHash {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+    }
+}
+
+impl PartialOrd for TopicHash {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TopicHash {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.hash.cmp(&other.hash)
+    }
 }
 
 impl TopicHash {
     pub fn from_raw(hash: impl Into<String>) -> TopicHash {
-        TopicHash { hash: hash.into() }
+        let hash = hash.into();
+        TopicHash {
+            hash: hash.clone().into_bytes(),
+            display: hash,
+        }
+    }
+
+    /// Builds a [`TopicHash`] from a raw digest, rendering it as base64.
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> TopicHash {
+        let hash = bytes.into();
+        let display = BASE64_STANDARD.encode(&hash);
+        TopicHash { hash, display }
+    }
+
+    /// Like [`TopicHash::from_bytes`], but renders as lowercase hex -- used by hashers
+    /// (e.g. [`Blake3Hash`]) that want iroh-style hex topic ids.
+    fn from_bytes_hex(bytes: impl Into<Vec<u8>>) -> TopicHash {
+        let hash = bytes.into();
+        let display = hash.iter().map(|b| format!("{b:02x}")).collect();
+        TopicHash { hash, display }
+    }
+
+    /// The raw digest bytes, independent of how this hash happens to render as text.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.hash
     }
 
     pub fn into_string(self) -> String {
-        self.hash
+        self.display
     }
 
     pub fn as_str(&self) -> &str {
-        &self.hash
+        &self.display
     }
 }
 
 /// A gossipsub topic.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// Only derives `PartialEq`, not `Eq`/`PartialOrd`/`Ord` as before: `auth`/`enc` are
+/// `proto`-generated types that don't implement those traits (`quick_protobuf` doesn't
+/// derive `Eq`/`Ord` for the message structs it generates). Callers who
+/// need a `Topic` in a `BTreeMap`/sorted `Vec` should key on [`TopicHash`] instead, which
+/// does implement them.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Topic<H: Hasher> {
     topic: String,
+    /// The authentication policy committed into this topic's hash, if any.
+    auth: Option<AuthOpts>,
+    /// The encryption policy committed into this topic's hash, if any.
+    enc: Option<EncOpts>,
     phantom_data: std::marker::PhantomData<H>,
 }
 
@@ -81,23 +199,387 @@ impl<H: Hasher> Topic<H> {
     pub fn new(topic: impl Into<String>) -> Self {
         Topic {
             topic: topic.into(),
+            auth: None,
+            enc: None,
+            phantom_data: std::marker::PhantomData,
+        }
+    }
+
+    /// Restricts this topic to an authentication policy (NONE/KEY/WOT plus a set of
+    /// signing public keys), committing it into the topic's hash so that topics with the
+    /// same name but different auth policies never collide.
+    pub fn with_auth(mut self, auth: AuthOpts) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Restricts this topic to an encryption policy (NONE/SHAREDSECRET/WOT plus a set of
+    /// key hashes), committing it into the topic's hash so that topics with the same name
+    /// but different encryption policies never collide.
+    pub fn with_enc(mut self, enc: EncOpts) -> Self {
+        self.enc = Some(enc);
+        self
+    }
+
+    /// Builds a topic from hierarchical name segments, e.g. `&["chat", "room", "42"]`.
+    ///
+    /// Each segment is prefixed with its own length before the next is appended, so two
+    /// different ways of splitting the same concatenated name can never produce the same
+    /// topic -- plain concatenation would let `["a", "bc"]` and `["ab", "c"]` collide, the
+    /// same segment-boundary ambiguity the ICS23 proof spec warns about. Use
+    /// [`Topic::hierarchical_segments`] to recover the segment list later.
+    pub fn hierarchical(segments: &[&str]) -> Self {
+        Topic {
+            topic: encode_hierarchical_segments(segments),
+            auth: None,
+            enc: None,
             phantom_data: std::marker::PhantomData,
         }
     }
 
+    /// Recovers the segment list from a topic built with [`Topic::hierarchical`].
+    ///
+    /// Returns `None` if this topic's name isn't validly length-prefixed, i.e. it wasn't
+    /// built with [`Topic::hierarchical`].
+    pub fn hierarchical_segments(&self) -> Option<Vec<&str>> {
+        decode_hierarchical_segments(&self.topic)
+    }
+
     pub fn hash(&self) -> TopicHash {
-        H::hash(self.topic.clone())
+        H::hash(self.topic.clone(), self.auth.clone(), self.enc.clone())
     }
 }
 
+/// Encodes `segments` with an explicit length prefix ahead of each one (e.g.
+/// `["chat", "room"]` becomes `"4:chat4:room"`), so the byte length can never be confused
+/// with the segment's own bytes. The prefix is ASCII decimal rather than a binary varint
+/// so the result stays valid UTF-8, since it's fed into the protobuf `name` field as-is.
+fn encode_hierarchical_segments(segments: &[&str]) -> String {
+    let mut encoded = String::new();
+    for segment in segments {
+        encoded.push_str(&segment.len().to_string());
+        encoded.push(':');
+        encoded.push_str(segment);
+    }
+    encoded
+}
+
+/// The inverse of [`encode_hierarchical_segments`].
+///
+/// `encoded` may come from an arbitrary [`Topic::new`] (e.g. a name received from a peer),
+/// not just [`Topic::hierarchical`], so a malformed length prefix -- including one that
+/// doesn't land on a UTF-8 char boundary -- must return `None` rather than panic.
+fn decode_hierarchical_segments(encoded: &str) -> Option<Vec<&str>> {
+    let mut segments = Vec::new();
+    let mut rest = encoded;
+    while !rest.is_empty() {
+        let (len_str, after_colon) = rest.split_once(':')?;
+        let len: usize = len_str.parse().ok()?;
+        if len > after_colon.len() || !after_colon.is_char_boundary(len) {
+            return None;
+        }
+        let (segment, remainder) = after_colon.split_at(len);
+        segments.push(segment);
+        rest = remainder;
+    }
+    Some(segments)
+}
+
+impl<H: Hasher + DynHasher + Default + 'static> Topic<H> {
+    /// Erases this topic's compile-time hasher type, returning an [`AnyTopic`] that can be
+    /// stored alongside topics using other hashers.
+    ///
+    /// `DynHasher` has no way to represent an auth/enc policy, so hashing through it would
+    /// silently drop the policy and make this topic collide with the unrestricted topic of
+    /// the same name -- exactly the collision [`Topic::with_auth`]/[`Topic::with_enc`] exist
+    /// to prevent. Rather than erase the policy silently, this fails with
+    /// [`TopicPolicyErasedError`] whenever one is set.
+    pub fn into_any(self) -> Result<AnyTopic, TopicPolicyErasedError> {
+        if self.auth.is_some() || self.enc.is_some() {
+            return Err(TopicPolicyErasedError);
+        }
+        Ok(AnyTopic::new(self.topic, Box::new(H::default())))
+    }
+}
+
+/// The error returned by [`Topic::into_any`] when the topic has an auth/enc policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicPolicyErasedError;
+
+impl fmt::Display for TopicPolicyErasedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot erase a topic's hasher type while it has an auth/enc policy: DynHasher \
+             can't represent the policy, so the resulting hash would silently drop it"
+        )
+    }
+}
+
+impl std::error::Error for TopicPolicyErasedError {}
+
 impl<H: Hasher> fmt::Display for Topic<H> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.topic)
     }
 }
 
+/// A gossipsub topic whose hasher is chosen at runtime rather than fixed at compile time.
+///
+/// [`Topic<H>`] remains a thin, zero-cost wrapper around a compile-time hasher; `AnyTopic`
+/// is the dynamic-dispatch counterpart for code that needs to hold topics using different
+/// hash algorithms in the same collection. Build one directly or via [`Topic::into_any`].
+#[derive(Debug)]
+pub struct AnyTopic {
+    topic: String,
+    hasher: Box<dyn DynHasher>,
+}
+
+impl AnyTopic {
+    pub fn new(topic: impl Into<String>, hasher: Box<dyn DynHasher>) -> Self {
+        AnyTopic {
+            topic: topic.into(),
+            hasher,
+        }
+    }
+
+    pub fn hash(&self) -> TopicHash {
+        self.hasher.hash(&self.topic)
+    }
+}
+
+impl fmt::Display for AnyTopic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.topic)
+    }
+}
+
 impl fmt::Display for TopicHash {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.hash)
+        write!(f, "{}", self.display)
+    }
+}
+
+// `#[derive(EncodeLabelSet)]` doesn't work here: `hash` is a `Vec<u8>`, which has no
+// `EncodeLabelValue` impl. Hand-roll it against `display` instead, which is the textual
+// form metrics want anyway.
+impl EncodeLabelSet for TopicHash {
+    fn encode(&self, mut encoder: LabelSetEncoder) -> Result<(), fmt::Error> {
+        let mut label_encoder = encoder.encode_label();
+        let mut label_key_encoder = label_encoder.encode_label_key()?;
+        fmt::Write::write_str(&mut label_key_encoder, "hash")?;
+        let mut label_value_encoder = label_key_encoder.encode_label_value()?;
+        EncodeLabelValue::encode(&self.display, &mut label_value_encoder)?;
+        label_value_encoder.finish()
+    }
+}
+
+/// The error returned when parsing a [`TopicHash`] from its base64 string form fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicHashParseError(base64::DecodeError);
+
+impl fmt::Display for TopicHashParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid topic hash: {}", self.0)
+    }
+}
+
+impl std::error::Error for TopicHashParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl FromStr for TopicHash {
+    type Err = TopicHashParseError;
+
+    /// Parses the two digest forms [`fmt::Display`] can produce: lowercase hex (as rendered
+    /// for a [`Blake3Hash`] topic) or base64 (as rendered for a [`Sha256Hash`] topic, and as
+    /// produced by a human-readable `Serialize`). Hex digits are a subset of the base64
+    /// alphabet, so a string of only lowercase hex digits is tried as hex first -- otherwise
+    /// a hex `Display` string would silently base64-decode to the wrong bytes instead of
+    /// round-tripping. The raw topic name that an [`IdentityHash`] topic displays as is not
+    /// recognized: it is decoded as hex or base64 like any other string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(bytes) = decode_lowercase_hex(s) {
+            return Ok(TopicHash::from_bytes_hex(bytes));
+        }
+        let bytes = BASE64_STANDARD.decode(s).map_err(TopicHashParseError)?;
+        Ok(TopicHash::from_bytes(bytes))
+    }
+}
+
+/// Decodes `s` as lowercase hex, returning `None` if it isn't one: odd length, empty, or
+/// any byte outside `0-9a-f`. Used by `FromStr` to recognize [`Blake3Hash`]'s `Display`
+/// form, since it can't be told apart from base64 by length alone.
+fn decode_lowercase_hex(s: &str) -> Option<Vec<u8>> {
+    if s.is_empty() || s.len() % 2 != 0 || !s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+impl TryFrom<&str> for TopicHash {
+    type Error = TopicHashParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl Serialize for TopicHash {
+    /// Serializes as a plain base64 string for human-readable formats (e.g. JSON), or as
+    /// the raw digest bytes for compact binary formats (e.g. postcard/bincode), so
+    /// `TopicHash` can be used directly as a map key in serialized configs without this
+    /// wrapper struct leaking into every format.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&BASE64_STANDARD.encode(&self.hash))
+        } else {
+            serializer.serialize_bytes(&self.hash)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TopicHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            s.parse().map_err(D::Error::custom)
+        } else {
+            let bytes = Vec::<u8>::deserialize(deserializer)?;
+            Ok(TopicHash::from_bytes(bytes))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blake3_topic_hash_survives_human_readable_round_trip() {
+        let hash = Blake3Hash::hash("chat/room/42".to_owned(), None, None);
+        let json = serde_json::to_string(&hash).expect("serialize to succeed");
+        let round_tripped: TopicHash = serde_json::from_str(&json).expect("deserialize to succeed");
+
+        assert_eq!(hash, round_tripped);
+        assert_eq!(hash.as_bytes(), round_tripped.as_bytes());
+
+        let mut set = std::collections::HashSet::new();
+        set.insert(hash);
+        assert!(set.contains(&round_tripped));
+    }
+
+    #[test]
+    fn blake3_topic_hash_display_round_trips_through_from_str() {
+        let hash = Blake3Hash::hash("chat/room/42".to_owned(), None, None);
+        let round_tripped: TopicHash = hash.to_string().parse().expect("parse to succeed");
+
+        assert_eq!(hash, round_tripped);
+        assert_eq!(hash.as_bytes(), round_tripped.as_bytes());
+    }
+
+    #[test]
+    fn sha256_topic_hash_display_round_trips_through_from_str() {
+        let hash = Sha256Hash::hash("chat/room/42".to_owned(), None, None);
+        let round_tripped: TopicHash = hash.to_string().parse().expect("parse to succeed");
+
+        assert_eq!(hash, round_tripped);
+        assert_eq!(hash.as_bytes(), round_tripped.as_bytes());
+    }
+
+    #[test]
+    fn into_any_rejects_a_topic_with_an_auth_policy() {
+        let open_topic = Topic::<Sha256Hash>::new("chat/room/42");
+        let restricted_topic =
+            Topic::<Sha256Hash>::new("chat/room/42").with_auth(AuthOpts::default());
+
+        assert!(open_topic.into_any().is_ok());
+        assert!(restricted_topic.into_any().is_err());
+    }
+
+    #[test]
+    fn auth_policy_changes_the_hash() {
+        use proto::mod_TopicDescriptor::mod_AuthOpts::AuthMode;
+
+        let open = Topic::<Sha256Hash>::new("chat/room/42");
+        let key_auth = Topic::<Sha256Hash>::new("chat/room/42").with_auth(AuthOpts {
+            mode: Some(AuthMode::KEY),
+            keys: vec![vec![1, 2, 3]],
+        });
+        let wot_auth = Topic::<Sha256Hash>::new("chat/room/42").with_auth(AuthOpts {
+            mode: Some(AuthMode::WOT),
+            keys: vec![vec![4, 5, 6]],
+        });
+
+        assert_ne!(open.hash(), key_auth.hash());
+        assert_ne!(open.hash(), wot_auth.hash());
+        assert_ne!(key_auth.hash(), wot_auth.hash());
+
+        // The same guarantee must hold for every `Hasher` that folds `auth`/`enc` into the
+        // hashed descriptor, not just `Sha256Hash`.
+        let open = Topic::<Blake3Hash>::new("chat/room/42");
+        let key_auth = Topic::<Blake3Hash>::new("chat/room/42").with_auth(AuthOpts {
+            mode: Some(AuthMode::KEY),
+            keys: vec![vec![1, 2, 3]],
+        });
+        assert_ne!(open.hash(), key_auth.hash());
+    }
+
+    #[test]
+    fn enc_policy_changes_the_hash() {
+        use proto::mod_TopicDescriptor::mod_EncOpts::EncMode;
+
+        let open = Topic::<Sha256Hash>::new("chat/room/42");
+        let shared_secret = Topic::<Sha256Hash>::new("chat/room/42").with_enc(EncOpts {
+            mode: Some(EncMode::SHAREDSECRET),
+            key_hashes: vec![vec![7, 8, 9]],
+        });
+        let wot_enc = Topic::<Sha256Hash>::new("chat/room/42").with_enc(EncOpts {
+            mode: Some(EncMode::WOT),
+            key_hashes: vec![vec![10, 11, 12]],
+        });
+
+        assert_ne!(open.hash(), shared_secret.hash());
+        assert_ne!(open.hash(), wot_enc.hash());
+        assert_ne!(shared_secret.hash(), wot_enc.hash());
+
+        let open = Topic::<Blake3Hash>::new("chat/room/42");
+        let shared_secret = Topic::<Blake3Hash>::new("chat/room/42").with_enc(EncOpts {
+            mode: Some(EncMode::SHAREDSECRET),
+            key_hashes: vec![vec![7, 8, 9]],
+        });
+        assert_ne!(open.hash(), shared_secret.hash());
+    }
+
+    #[test]
+    fn hierarchical_segments_round_trip() {
+        let topic = Topic::<IdentityHash>::hierarchical(&["chat", "room", "42"]);
+
+        assert_eq!(
+            topic.hierarchical_segments(),
+            Some(vec!["chat", "room", "42"])
+        );
+    }
+
+    #[test]
+    fn hierarchical_segments_do_not_collide_across_different_splits() {
+        let ab_c = Topic::<IdentityHash>::hierarchical(&["ab", "c"]);
+        let a_bc = Topic::<IdentityHash>::hierarchical(&["a", "bc"]);
+
+        assert_ne!(ab_c.hash(), a_bc.hash());
+    }
+
+    #[test]
+    fn hierarchical_segments_rejects_a_length_prefix_that_splits_a_char() {
+        // "é" is two bytes; a claimed length of 1 lands inside it rather than on a
+        // char boundary, and must be rejected instead of panicking.
+        let topic = Topic::<IdentityHash>::new("1:é");
+
+        assert_eq!(topic.hierarchical_segments(), None);
     }
 }